@@ -9,6 +9,21 @@ use std::{cmp, collections::BTreeSet};
 pub struct StatusTree {
     pub tree: FileTreeItems,
     pub selection: Option<usize>,
+    /// index of the first visible item currently scrolled to the top of the viewport
+    pub scroll_top: usize,
+    /// the full, unfiltered master list this tree was built from; `filter`
+    /// always re-derives from it (never from an already-filtered `tree`) so
+    /// clearing or widening a filter can recover previously dropped items
+    files: Vec<StatusItem>,
+}
+
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VisualSelection {
+    /// amount of currently visible (non-collapsed) items
+    pub count: usize,
+    /// position of the selection among the visible items
+    pub index: usize,
 }
 
 ///
@@ -18,6 +33,14 @@ pub enum MoveSelection {
     Down,
     Left,
     Right,
+    /// jump to the first visible item
+    Top,
+    /// jump to the last visible item
+    End,
+    /// move up by a viewport's worth of visible rows
+    PageUp,
+    /// move down by a viewport's worth of visible rows
+    PageDown,
 }
 
 struct SelectionChange {
@@ -40,6 +63,7 @@ impl StatusTree {
         let last_selection_index = self.selection.unwrap_or(0);
 
         self.tree = FileTreeItems::new(list, &last_collapsed);
+        self.files = list.to_vec();
         self.selection =
             if let Some(ref last_selection) = last_selection {
                 self.find_last_selection(
@@ -53,10 +77,22 @@ impl StatusTree {
             };
 
         self.update_visibility(None, 0, true);
+
+        // the tree may have shrunk since the last update; without this a stale
+        // `scroll_top` can skip past every remaining visible item
+        let visible_count =
+            self.tree.items().iter().filter(|i| i.info.visible).count();
+        self.scroll_top =
+            self.scroll_top.min(visible_count.saturating_sub(1));
     }
 
-    ///
-    pub fn move_selection(&mut self, dir: MoveSelection) -> bool {
+    /// `height` is the number of visible rows the caller can currently draw;
+    /// `scroll_top` is adjusted so the selection stays inside that window
+    pub fn move_selection(
+        &mut self,
+        dir: MoveSelection,
+        height: usize,
+    ) -> bool {
         if let Some(selection) = self.selection {
             let selection_change = match dir {
                 MoveSelection::Up => {
@@ -70,12 +106,28 @@ impl StatusTree {
                 MoveSelection::Right => {
                     self.selection_right(selection)
                 }
+                MoveSelection::Top => SelectionChange::new(
+                    self.first_visible_index(),
+                    false,
+                ),
+                MoveSelection::End => SelectionChange::new(
+                    self.last_visible_index(),
+                    false,
+                ),
+                MoveSelection::PageUp => {
+                    self.selection_page(selection, height, true)
+                }
+                MoveSelection::PageDown => {
+                    self.selection_page(selection, height, false)
+                }
             };
 
             let changed = selection_change.new_index != selection;
 
             self.selection = Some(selection_change.new_index);
 
+            self.update_scroll(height);
+
             changed || selection_change.changes
         } else {
             false
@@ -92,6 +144,93 @@ impl StatusTree {
         self.tree.items().is_empty()
     }
 
+    /// count of visible items and the selection's position among them
+    pub fn visual_selection(&self) -> Option<VisualSelection> {
+        let selection = self.selection?;
+
+        let mut index = 0;
+        let mut count = 0;
+
+        for (i, item) in self.tree.items().iter().enumerate() {
+            if !item.info.visible {
+                continue;
+            }
+
+            if i == selection {
+                index = count;
+            }
+
+            count += 1;
+        }
+
+        Some(VisualSelection { count, index })
+    }
+
+    /// iterator over the visible items currently inside the `[scroll_top, scroll_top+height)` window
+    pub fn visible_items(
+        &self,
+        height: usize,
+    ) -> impl Iterator<Item = &FileTreeItem> {
+        self.tree
+            .items()
+            .iter()
+            .filter(|i| i.info.visible)
+            .skip(self.scroll_top)
+            .take(height)
+    }
+
+    /// build a new `StatusTree` containing only files whose `full_path` contains
+    /// `text` (case-insensitive), plus the ancestor directories needed to reach
+    /// them; an empty `text` returns the full, unfiltered tree
+    pub fn filter(&self, text: &str) -> Self {
+        let needle = text.to_lowercase();
+        let collapsed = self.all_collapsed();
+
+        // always filter from the master list, never from `self.tree`: on an
+        // already-filtered tree that would only ever narrow further, and
+        // clearing/widening the filter needs to recover items a previous,
+        // narrower filter dropped
+        let matching: Vec<StatusItem> = if needle.is_empty() {
+            self.files.clone()
+        } else {
+            self.files
+                .iter()
+                .filter(|item| item.path.to_lowercase().contains(&needle))
+                .cloned()
+                .collect()
+        };
+
+        let tree = FileTreeItems::new(&matching, &collapsed);
+        let selection = tree.items().first().map(|_| 0);
+
+        let mut result = Self {
+            tree,
+            selection,
+            scroll_top: 0,
+            files: self.files.clone(),
+        };
+
+        result.update_visibility(None, 0, true);
+
+        result
+    }
+
+    fn update_scroll(&mut self, height: usize) {
+        if height == 0 {
+            return;
+        }
+
+        if let Some(VisualSelection { index, .. }) =
+            self.visual_selection()
+        {
+            if index < self.scroll_top {
+                self.scroll_top = index;
+            } else if index >= self.scroll_top + height {
+                self.scroll_top = index + 1 - height;
+            }
+        }
+    }
+
     fn all_collapsed(&self) -> BTreeSet<&String> {
         let mut res = BTreeSet::new();
 
@@ -162,6 +301,76 @@ impl StatusTree {
         self.tree[idx].info.visible
     }
 
+    fn first_visible_index(&self) -> usize {
+        self.tree
+            .items()
+            .iter()
+            .position(|i| i.info.visible)
+            .unwrap_or(0)
+    }
+
+    fn last_visible_index(&self) -> usize {
+        self.tree
+            .items()
+            .iter()
+            .rposition(|i| i.info.visible)
+            .unwrap_or(0)
+    }
+
+    /// moves up to `height` visible rows from `current_index`, stopping early at the edge
+    fn selection_page(
+        &self,
+        current_index: usize,
+        height: usize,
+        up: bool,
+    ) -> SelectionChange {
+        let mut index = current_index;
+
+        for _ in 0..height {
+            let next = self.selection_updown(index, up).new_index;
+            if next == index {
+                break;
+            }
+            index = next;
+        }
+
+        SelectionChange::new(index, false)
+    }
+
+    /// collapses every path node below the top level, leaving only root-level
+    /// entries expanded (their own children, in turn, stay collapsed)
+    pub fn collapse_all_but_root(&mut self) {
+        let sub_paths: Vec<(String, usize)> = self
+            .tree
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| {
+                matches!(item.kind, FileTreeItemKind::Path(_))
+                    && item.info.full_path.contains('/')
+            })
+            .map(|(i, item)| (item.info.full_path.clone(), i))
+            .collect();
+
+        for (path, index) in sub_paths {
+            self.collapse(&path, index);
+        }
+
+        if let Some(selection) = self.selection {
+            if !self.is_visible_index(selection) {
+                let path = self.tree[selection].info.full_path.clone();
+                self.selection = Some(
+                    self.tree.find_parent_index(&path, selection),
+                );
+            }
+        }
+
+        let visible_count =
+            self.tree.items().iter().filter(|i| i.info.visible).count();
+        self.scroll_top =
+            self.scroll_top.min(visible_count.saturating_sub(1));
+    }
+
     fn selection_right(
         &mut self,
         current_selection: usize,
@@ -324,11 +533,11 @@ mod tests {
         let mut res = StatusTree::default();
         res.update(&items);
 
-        assert!(res.move_selection(MoveSelection::Down));
+        assert!(res.move_selection(MoveSelection::Down, 10));
 
         assert_eq!(res.selection, Some(1));
 
-        assert!(res.move_selection(MoveSelection::Left));
+        assert!(res.move_selection(MoveSelection::Left, 10));
 
         assert_eq!(res.selection, Some(0));
     }
@@ -594,8 +803,258 @@ mod tests {
         res.collapse(&String::from("a/b"), 1);
         res.selection = Some(1);
 
-        assert!(res.move_selection(MoveSelection::Down));
+        assert!(res.move_selection(MoveSelection::Down, 10));
 
         assert_eq!(res.selection, Some(3));
     }
+
+    #[test]
+    fn test_scroll_top_follows_selection() {
+        let items = string_vec_to_status(&[
+            "a", "b", "c", "d", "e", "f",
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        // viewport only shows 3 rows at a time
+        for _ in 0..4 {
+            res.move_selection(MoveSelection::Down, 3);
+        }
+
+        assert_eq!(res.selection, Some(4));
+        assert_eq!(res.scroll_top, 2);
+
+        let visible: Vec<_> = res
+            .visible_items(3)
+            .map(|i| i.info.full_path.clone())
+            .collect();
+
+        assert_eq!(
+            visible,
+            vec![
+                String::from("c"),
+                String::from("d"),
+                String::from("e"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_clamps_scroll_top_when_tree_shrinks() {
+        let items = string_vec_to_status(&["a", "b", "c", "d", "e", "f"]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+        res.scroll_top = 5;
+
+        res.update(&string_vec_to_status(&["a", "b"]));
+
+        assert_eq!(res.scroll_top, 1);
+    }
+
+    #[test]
+    fn test_visual_selection_counts_only_visible() {
+        let items = string_vec_to_status(&[
+            "a/b/c", //
+            "a/d",   //
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+        res.collapse(&String::from("a/b"), 1);
+        res.selection = Some(3);
+
+        let visual = res.visual_selection().unwrap();
+
+        assert_eq!(visual.count, 3);
+        assert_eq!(visual.index, 2);
+    }
+
+    #[test]
+    fn test_filter_keeps_matches_and_ancestors() {
+        let items = string_vec_to_status(&[
+            "src/foo.rs", //
+            "src/bar.rs", //
+            "docs/readme.md",
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        let filtered = res.filter("foo");
+
+        let paths = filtered
+            .tree
+            .items()
+            .iter()
+            .map(|i| i.info.full_path.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            vec![
+                String::from("src"),
+                String::from("src/foo.rs"),
+            ]
+        );
+        assert_eq!(filtered.selection, Some(0));
+    }
+
+    #[test]
+    fn test_filter_empty_restores_full_tree() {
+        let items = string_vec_to_status(&[
+            "src/foo.rs", //
+            "docs/readme.md",
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        let filtered = res.filter("");
+
+        assert_eq!(filtered.tree.len(), res.tree.len());
+    }
+
+    #[test]
+    fn test_filter_then_widen_recovers_dropped_items() {
+        let items = string_vec_to_status(&[
+            "src/foo.rs", //
+            "src/bar.rs", //
+            "docs/readme.md",
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        // narrow down to a single file...
+        let narrowed = res.filter("foo");
+        assert_eq!(narrowed.tree.len(), 2); // src/, src/foo.rs
+
+        // ...then widen the filter on the *narrowed* tree
+        let widened = narrowed.filter("src");
+
+        let paths = widened
+            .tree
+            .items()
+            .iter()
+            .map(|i| i.info.full_path.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            vec![
+                String::from("src"),
+                String::from("src/bar.rs"),
+                String::from("src/foo.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_preserves_collapsed_descendant_visibility() {
+        let items = string_vec_to_status(&[
+            "src/foo.rs", //
+            "src/bar.rs", //
+        ]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+        res.collapse(&String::from("src"), 0);
+
+        let filtered = res.filter("src");
+
+        assert_eq!(
+            get_visibles(&filtered),
+            vec![
+                true,  // src/
+                false, //   bar.rs
+                false, //   foo.rs
+            ]
+        );
+    }
+
+    #[test]
+    fn test_top_and_end() {
+        let items = string_vec_to_status(&["a", "b", "c"]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+        res.selection = Some(1);
+
+        res.move_selection(MoveSelection::End, 10);
+        assert_eq!(res.selection, Some(2));
+
+        res.move_selection(MoveSelection::Top, 10);
+        assert_eq!(res.selection, Some(0));
+    }
+
+    #[test]
+    fn test_page_down_stops_at_last_visible() {
+        let items = string_vec_to_status(&["a", "b", "c", "d"]);
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        res.move_selection(MoveSelection::PageDown, 2);
+        assert_eq!(res.selection, Some(2));
+
+        res.move_selection(MoveSelection::PageDown, 2);
+        assert_eq!(res.selection, Some(3));
+    }
+
+    #[test]
+    fn test_collapse_all_but_root() {
+        let items = string_vec_to_status(&[
+            "a/b/c", //
+            "a/d",   //
+            "e",     //
+        ]);
+
+        //0 a/
+        //1   b/
+        //2     c
+        //3   d
+        //4 e
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+
+        res.collapse_all_but_root();
+
+        assert_eq!(
+            get_visibles(&res),
+            vec![
+                true,  // a/
+                true,  //   b/
+                false, //     c
+                true,  //   d
+                true,  // e
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_all_but_root_clamps_scroll_top() {
+        let items = string_vec_to_status(&[
+            "a/b/c", //
+            "a/d",   //
+            "e",     //
+        ]);
+
+        //0 a/
+        //1   b/
+        //2     c
+        //3   d
+        //4 e
+
+        let mut res = StatusTree::default();
+        res.update(&items);
+        res.scroll_top = 4;
+
+        res.collapse_all_but_root();
+
+        // collapsing hides "c", leaving only 4 visible items (indices 0-3)
+        assert_eq!(res.scroll_top, 3);
+    }
 }
@@ -1,6 +1,18 @@
-use git2::{DiffFormat, DiffOptions, Repository};
+use git2::{Diff as GitDiff, DiffFormat, DiffOptions, Oid, Repository};
+use once_cell::sync::Lazy;
+use std::ops::Range;
 use std::path::Path;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Style as SyntectStyle, ThemeSet};
+use syntect::parsing::SyntaxSet;
 use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Style};
+
+/// bundled syntax definitions, loaded once and reused across every highlighted diff
+static SYNTAX_SET: Lazy<SyntaxSet> =
+    Lazy::new(SyntaxSet::load_defaults_newlines);
+/// bundled color themes, loaded once and reused across every highlighted diff
+static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
 
 ///
 #[derive(Copy, Clone, PartialEq)]
@@ -22,20 +34,38 @@ impl Default for DiffLineType {
 pub struct DiffLine {
     pub content: String,
     pub line_type: DiffLineType,
+    /// per-token foreground styles, populated only when `get_diff` is called with `highlight: true`
+    pub styles: Vec<(Style, Range<usize>)>,
+    /// byte ranges unique to this side of a matched delete/add line pair, used
+    /// to render word-level diff highlights; empty for unpaired/context lines
+    pub highlights: Vec<Range<usize>>,
 }
 
 ///
 #[derive(Default, PartialEq)]
 pub struct Diff(pub Vec<DiffLine>);
 
+/// what two trees/states `get_diff` should compare
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiffTarget {
+    /// unstaged changes: index vs. worktree (the previous, only, behavior)
+    WorkdirToIndex,
+    /// staged changes: HEAD vs. index
+    IndexToHead,
+    /// a single commit's changes against its first parent
+    CommitToParent(Oid),
+    /// changes between two arbitrary revisions
+    Range(Oid, Oid),
+}
+
 ///
-pub fn get_diff(p: &Path) -> Diff {
+pub fn get_diff(p: &Path, target: DiffTarget, highlight: bool) -> Diff {
     let repo = repo();
 
     let mut opt = DiffOptions::new();
     opt.pathspec(p);
 
-    let diff = repo.diff_index_to_workdir(None, Some(&mut opt)).unwrap();
+    let diff = diff_for_target(&repo, target, &mut opt);
 
     let mut res = Vec::new();
 
@@ -53,12 +83,16 @@ pub fn get_diff(p: &Path) -> Diff {
             let diff_line = DiffLine {
                 content: String::from_utf8_lossy(line.content()).to_string(),
                 line_type,
+                styles: Vec::new(),
+                highlights: Vec::new(),
             };
 
             if line_type == DiffLineType::Header && res.len() > 0 {
                 res.push(DiffLine {
                     content: "\n".to_string(),
                     line_type: DiffLineType::None,
+                    styles: Vec::new(),
+                    highlights: Vec::new(),
                 });
             }
 
@@ -68,9 +102,261 @@ pub fn get_diff(p: &Path) -> Diff {
     })
     .unwrap();
 
+    mark_word_diffs(&mut res);
+
+    if highlight {
+        highlight_lines(p, &mut res);
+    }
+
     Diff(res)
 }
 
+/// resolves a `DiffTarget` to the matching `git2` two-sided diff
+fn diff_for_target<'repo>(
+    repo: &'repo Repository,
+    target: DiffTarget,
+    opt: &mut DiffOptions,
+) -> GitDiff<'repo> {
+    match target {
+        DiffTarget::WorkdirToIndex => {
+            repo.diff_index_to_workdir(None, Some(opt)).unwrap()
+        }
+        DiffTarget::IndexToHead => {
+            // `head()` errors with `UnbornBranch` on a fresh repo with no commits
+            // yet; diff against an empty tree in that case, same as
+            // `CommitToParent` does for a root commit
+            let head_tree = match repo.head() {
+                Ok(head) => Some(head.peel_to_tree().unwrap()),
+                Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+                    None
+                }
+                Err(e) => panic!("{}", e),
+            };
+
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(opt))
+                .unwrap()
+        }
+        DiffTarget::CommitToParent(oid) => {
+            let commit = repo.find_commit(oid).unwrap();
+            let tree = commit.tree().unwrap();
+            let parent_tree =
+                commit.parent(0).ok().and_then(|p| p.tree().ok());
+
+            repo.diff_tree_to_tree(
+                parent_tree.as_ref(),
+                Some(&tree),
+                Some(opt),
+            )
+            .unwrap()
+        }
+        DiffTarget::Range(old, new) => {
+            let old_tree = repo.find_commit(old).unwrap().tree().unwrap();
+            let new_tree = repo.find_commit(new).unwrap().tree().unwrap();
+
+            repo.diff_tree_to_tree(
+                Some(&old_tree),
+                Some(&new_tree),
+                Some(opt),
+            )
+            .unwrap()
+        }
+    }
+}
+
+/// caps the token count per line pair so a pathological minified line can't
+/// blow up the O(n·m) LCS table
+const MAX_DIFF_TOKENS: usize = 500;
+
+/// for each run of consecutive `Delete` lines immediately followed by an equal
+/// number of `Add` lines, pairs them up positionally and records the byte
+/// ranges unique to each side on `DiffLine::highlights`
+fn mark_word_diffs(lines: &mut [DiffLine]) {
+    let mut i = 0;
+
+    while i < lines.len() {
+        if lines[i].line_type != DiffLineType::Delete {
+            i += 1;
+            continue;
+        }
+
+        let del_start = i;
+        let mut del_end = del_start;
+        while del_end < lines.len()
+            && lines[del_end].line_type == DiffLineType::Delete
+        {
+            del_end += 1;
+        }
+
+        let add_start = del_end;
+        let mut add_end = add_start;
+        while add_end < lines.len()
+            && lines[add_end].line_type == DiffLineType::Add
+        {
+            add_end += 1;
+        }
+
+        let del_count = del_end - del_start;
+        let add_count = add_end - add_start;
+
+        if del_count > 0 && del_count == add_count {
+            for k in 0..del_count {
+                let (del_highlights, add_highlights) = word_diff_ranges(
+                    &lines[del_start + k].content,
+                    &lines[add_start + k].content,
+                );
+                lines[del_start + k].highlights = del_highlights;
+                lines[add_start + k].highlights = add_highlights;
+            }
+        }
+
+        i = add_end.max(del_end);
+    }
+}
+
+/// tokenizes `deleted`/`added` into whitespace/word runs, runs an LCS token
+/// diff between them, and returns the byte ranges unique to each side
+fn word_diff_ranges(
+    deleted: &str,
+    added: &str,
+) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let del_tokens = tokenize(deleted);
+    let add_tokens = tokenize(added);
+
+    if del_tokens.len() > MAX_DIFF_TOKENS
+        || add_tokens.len() > MAX_DIFF_TOKENS
+    {
+        return (Vec::new(), Vec::new());
+    }
+
+    let del_words: Vec<&str> =
+        del_tokens.iter().map(|r| &deleted[r.clone()]).collect();
+    let add_words: Vec<&str> =
+        add_tokens.iter().map(|r| &added[r.clone()]).collect();
+
+    let mut del_matched = vec![false; del_tokens.len()];
+    let mut add_matched = vec![false; add_tokens.len()];
+
+    for (i, j) in lcs_matched_pairs(&del_words, &add_words) {
+        del_matched[i] = true;
+        add_matched[j] = true;
+    }
+
+    let unmatched = |tokens: &[Range<usize>], matched: &[bool]| {
+        tokens
+            .iter()
+            .zip(matched.iter())
+            .filter(|(_, matched)| !**matched)
+            .map(|(range, _)| range.clone())
+            .collect()
+    };
+
+    (
+        unmatched(&del_tokens, &del_matched),
+        unmatched(&add_tokens, &add_matched),
+    )
+}
+
+/// splits `s` into maximal runs of whitespace/non-whitespace chars
+fn tokenize(s: &str) -> Vec<Range<usize>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let is_whitespace = c.is_whitespace();
+        let mut end = start + c.len_utf8();
+        chars.next();
+
+        while let Some(&(idx, next)) = chars.peek() {
+            if next.is_whitespace() != is_whitespace {
+                break;
+            }
+            end = idx + next.len_utf8();
+            chars.next();
+        }
+
+        tokens.push(start..end);
+    }
+
+    tokens
+}
+
+/// classic O(n·m) LCS table, backtracked into the list of matched `(a_index, b_index)` pairs
+fn lcs_matched_pairs(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut dp = vec![vec![0u32; m + 1]; n + 1];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            dp[i][j] = if a[i] == b[j] {
+                dp[i + 1][j + 1] + 1
+            } else {
+                dp[i + 1][j].max(dp[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if dp[i + 1][j] >= dp[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+/// runs a `syntect` highlighter (picked from `path`'s extension, falling back to
+/// plain text) over every non-header line and records the resulting foreground
+/// spans on `DiffLine::styles`; this composes with the add/delete background
+/// coloring the renderer already applies
+fn highlight_lines(path: &Path, lines: &mut [DiffLine]) {
+    let syntax = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| SYNTAX_SET.find_syntax_by_extension(ext))
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+
+    let theme = &THEME_SET.themes["base16-ocean.dark"];
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    for line in lines.iter_mut() {
+        if line.line_type == DiffLineType::Header {
+            continue;
+        }
+
+        let ranges =
+            match highlighter.highlight_line(&line.content, &SYNTAX_SET) {
+                Ok(ranges) => ranges,
+                Err(_) => continue,
+            };
+
+        let mut offset = 0;
+        line.styles = ranges
+            .into_iter()
+            .map(|(style, text)| {
+                let start = offset;
+                offset += text.len();
+                (to_tui_style(style), start..offset)
+            })
+            .collect();
+    }
+}
+
+fn to_tui_style(style: SyntectStyle) -> Style {
+    Style::default().fg(Color::Rgb(
+        style.foreground.r,
+        style.foreground.g,
+        style.foreground.b,
+    ))
+}
+
 ///
 pub fn repo() -> Repository {
     let repo = Repository::init("./").unwrap();
@@ -131,4 +417,169 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
             .as_ref(),
         )
         .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tokenize_splits_words_and_whitespace() {
+        let tokens = tokenize("foo  bar");
+
+        let slices: Vec<&str> =
+            tokens.iter().map(|r| &"foo  bar"[r.clone()]).collect();
+
+        assert_eq!(slices, vec!["foo", "  ", "bar"]);
+    }
+
+    #[test]
+    fn test_tokenize_handles_multi_byte_utf8() {
+        let s = "héllo wörld";
+        let tokens = tokenize(s);
+
+        let slices: Vec<&str> =
+            tokens.iter().map(|r| &s[r.clone()]).collect();
+
+        assert_eq!(slices, vec!["héllo", " ", "wörld"]);
+    }
+
+    #[test]
+    fn test_lcs_matched_pairs_finds_common_subsequence() {
+        let a = vec!["a", "b", "c"];
+        let b = vec!["x", "a", "c"];
+
+        assert_eq!(lcs_matched_pairs(&a, &b), vec![(0, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_word_diff_ranges_marks_only_changed_words() {
+        let (del, add) = word_diff_ranges("foo bar", "foo baz");
+
+        // "foo " is common to both sides, only the last word differs
+        assert_eq!(del, vec![4..7]);
+        assert_eq!(add, vec![4..7]);
+    }
+
+    #[test]
+    fn test_word_diff_ranges_unequal_lengths() {
+        let (del, add) = word_diff_ranges("foo", "foo bar");
+
+        assert_eq!(del, Vec::<Range<usize>>::new());
+        assert_eq!(add, vec![3..4, 4..7]);
+    }
+
+    #[test]
+    fn test_word_diff_ranges_respects_max_token_cap() {
+        let huge = "a ".repeat(MAX_DIFF_TOKENS + 1);
+
+        let (del, add) = word_diff_ranges(&huge, "a");
+
+        assert!(del.is_empty());
+        assert!(add.is_empty());
+    }
+
+    fn init_repo() -> (tempfile::TempDir, Repository) {
+        let dir = tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        (dir, repo)
+    }
+
+    fn commit_all(repo: &Repository, msg: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree_id = index.write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let sig = git2::Signature::now("test", "test@test").unwrap();
+        let parents = match repo.head().ok().and_then(|h| h.target()) {
+            Some(oid) => vec![repo.find_commit(oid).unwrap()],
+            None => Vec::new(),
+        };
+        let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+        repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &parent_refs)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_diff_for_target_index_to_head_on_unborn_branch() {
+        let (dir, repo) = init_repo();
+        fs::write(dir.path().join("a.txt"), "hello\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+
+        let mut opt = DiffOptions::new();
+        let diff =
+            diff_for_target(&repo, DiffTarget::IndexToHead, &mut opt);
+
+        assert_eq!(diff.deltas().len(), 1);
+    }
+
+    #[test]
+    fn test_diff_for_target_commit_to_parent_on_root_commit() {
+        let (dir, repo) = init_repo();
+        fs::write(dir.path().join("a.txt"), "hello\n").unwrap();
+        let oid = commit_all(&repo, "root commit");
+
+        let mut opt = DiffOptions::new();
+        let diff = diff_for_target(
+            &repo,
+            DiffTarget::CommitToParent(oid),
+            &mut opt,
+        );
+
+        assert_eq!(diff.deltas().len(), 1);
+    }
+
+    #[test]
+    fn test_diff_for_target_range_between_two_commits() {
+        let (dir, repo) = init_repo();
+        fs::write(dir.path().join("a.txt"), "hello\n").unwrap();
+        let first = commit_all(&repo, "first");
+
+        fs::write(dir.path().join("b.txt"), "world\n").unwrap();
+        let second = commit_all(&repo, "second");
+
+        let mut opt = DiffOptions::new();
+        let diff = diff_for_target(
+            &repo,
+            DiffTarget::Range(first, second),
+            &mut opt,
+        );
+
+        assert_eq!(diff.deltas().len(), 1);
+    }
+
+    #[test]
+    fn test_highlight_lines_attaches_styles_to_content_lines() {
+        let mut lines = vec![
+            DiffLine {
+                content: "@@ -1 +1 @@".to_string(),
+                line_type: DiffLineType::Header,
+                styles: Vec::new(),
+                highlights: Vec::new(),
+            },
+            DiffLine {
+                content: "fn main() {}".to_string(),
+                line_type: DiffLineType::Add,
+                styles: Vec::new(),
+                highlights: Vec::new(),
+            },
+        ];
+
+        highlight_lines(Path::new("main.rs"), &mut lines);
+
+        assert!(lines[0].styles.is_empty());
+        assert!(!lines[1].styles.is_empty());
+    }
 }
\ No newline at end of file
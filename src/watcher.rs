@@ -0,0 +1,289 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    thread,
+    time::Duration,
+};
+
+/// how long to keep absorbing further fs events before emitting a single `RepoChange`
+const DEBOUNCE_MS: u64 = 400;
+
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RepoChange {
+    /// a tracked (non-ignored) worktree file was created/modified/removed
+    Status,
+    /// `.git/HEAD` or the index changed, e.g. a commit, checkout, or stage/unstage
+    Head,
+}
+
+/// watches a repository worktree with `notify` and emits debounced `RepoChange`
+/// events on `receiver()`, skipping `.gitignore`d paths and most of `.git/`
+pub struct RepoWatcher {
+    // kept alive so the OS watch stays registered; never read directly
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<RepoChange>,
+}
+
+impl RepoWatcher {
+    ///
+    pub fn new(repo_path: &Path) -> notify::Result<Self> {
+        let (raw_tx, raw_rx) = channel();
+
+        let mut watcher: RecommendedWatcher =
+            Watcher::new(raw_tx, Duration::from_millis(DEBOUNCE_MS))?;
+        watcher.watch(repo_path, RecursiveMode::Recursive)?;
+
+        let (tx, rx) = channel();
+
+        let ignore = build_ignore(repo_path);
+        let repo_path = repo_path.to_path_buf();
+
+        thread::spawn(move || {
+            run_debounce_loop(&repo_path, &ignore, &raw_rx, &tx)
+        });
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver: rx,
+        })
+    }
+
+    /// channel half the app loop can `select!`/`recv` on for repo-changed events
+    pub fn receiver(&self) -> &Receiver<RepoChange> {
+        &self.receiver
+    }
+}
+
+/// builds an ignore matcher from every `.gitignore` found under `repo_path`
+/// (not just the root one); all patterns are matched relative to `repo_path`
+/// as if they were in the root file, so a nested file's rules also apply
+/// outside its own directory and its anchored patterns are anchored at
+/// `repo_path`
+fn build_ignore(repo_path: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(repo_path);
+    collect_gitignores(repo_path, &mut builder);
+    builder.build().unwrap_or_else(|_| {
+        GitignoreBuilder::new(repo_path).build().expect("empty gitignore builder")
+    })
+}
+
+fn collect_gitignores(dir: &Path, builder: &mut GitignoreBuilder) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if path.file_name().and_then(|n| n.to_str()) == Some(".git") {
+                continue;
+            }
+            collect_gitignores(&path, builder);
+        } else if path.file_name().and_then(|n| n.to_str())
+            == Some(".gitignore")
+        {
+            let _ = builder.add(&path);
+        }
+    }
+}
+
+/// `Head` always wins: a HEAD/index change must never be downgraded to a
+/// plain `Status` refresh just because a worktree write happened to land
+/// later in the same debounce window
+fn merge_change(
+    current: Option<RepoChange>,
+    new: Option<RepoChange>,
+) -> Option<RepoChange> {
+    match (current, new) {
+        (Some(RepoChange::Head), _) | (_, Some(RepoChange::Head)) => {
+            Some(RepoChange::Head)
+        }
+        (Some(change), _) | (None, Some(change)) => Some(change),
+        (None, None) => None,
+    }
+}
+
+fn run_debounce_loop(
+    repo_path: &Path,
+    ignore: &Gitignore,
+    raw_rx: &Receiver<DebouncedEvent>,
+    tx: &std::sync::mpsc::Sender<RepoChange>,
+) {
+    while let Ok(event) = raw_rx.recv() {
+        let mut change = classify(repo_path, ignore, &event);
+
+        // coalesce any further events arriving within the debounce window into one
+        while let Ok(next) =
+            raw_rx.recv_timeout(Duration::from_millis(DEBOUNCE_MS))
+        {
+            change = merge_change(change, classify(repo_path, ignore, &next));
+        }
+
+        if let Some(change) = change {
+            if tx.send(change).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn classify(
+    repo_path: &Path,
+    ignore: &Gitignore,
+    event: &DebouncedEvent,
+) -> Option<RepoChange> {
+    let path = changed_path(event)?;
+    let relative = path.strip_prefix(repo_path).unwrap_or(path);
+
+    if let Ok(git_relative) = relative.strip_prefix(".git") {
+        return match git_relative.to_str() {
+            Some("HEAD") => Some(RepoChange::Head),
+            Some(p) if p.starts_with("index") => Some(RepoChange::Head),
+            _ => None,
+        };
+    }
+
+    // parents are checked too, so files inside an ignored directory are skipped
+    if relative.is_relative()
+        && ignore
+            .matched_path_or_any_parents(relative, path.is_dir())
+            .is_ignore()
+    {
+        return None;
+    }
+
+    Some(RepoChange::Status)
+}
+
+fn changed_path(event: &DebouncedEvent) -> Option<&PathBuf> {
+    match event {
+        DebouncedEvent::Create(p)
+        | DebouncedEvent::Write(p)
+        | DebouncedEvent::Chmod(p)
+        | DebouncedEvent::Remove(p) => Some(p),
+        DebouncedEvent::Rename(_, p) => Some(p),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_classify_head_change() {
+        let dir = tempdir().unwrap();
+        let ignore = build_ignore(dir.path());
+        let event = DebouncedEvent::Write(dir.path().join(".git/HEAD"));
+
+        assert_eq!(
+            classify(dir.path(), &ignore, &event),
+            Some(RepoChange::Head)
+        );
+    }
+
+    #[test]
+    fn test_classify_index_change() {
+        let dir = tempdir().unwrap();
+        let ignore = build_ignore(dir.path());
+        let event = DebouncedEvent::Write(dir.path().join(".git/index"));
+
+        assert_eq!(
+            classify(dir.path(), &ignore, &event),
+            Some(RepoChange::Head)
+        );
+    }
+
+    #[test]
+    fn test_classify_other_git_path_is_ignored() {
+        let dir = tempdir().unwrap();
+        let ignore = build_ignore(dir.path());
+        let event = DebouncedEvent::Write(
+            dir.path().join(".git/refs/heads/master"),
+        );
+
+        assert_eq!(classify(dir.path(), &ignore, &event), None);
+    }
+
+    #[test]
+    fn test_classify_tracked_worktree_path_is_status() {
+        let dir = tempdir().unwrap();
+        let ignore = build_ignore(dir.path());
+        let event = DebouncedEvent::Write(dir.path().join("src/main.rs"));
+
+        assert_eq!(
+            classify(dir.path(), &ignore, &event),
+            Some(RepoChange::Status)
+        );
+    }
+
+    #[test]
+    fn test_classify_ignored_worktree_path_is_none() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target\n").unwrap();
+        let ignore = build_ignore(dir.path());
+        let event =
+            DebouncedEvent::Write(dir.path().join("target/debug/out"));
+
+        assert_eq!(classify(dir.path(), &ignore, &event), None);
+    }
+
+    #[test]
+    fn test_classify_nested_gitignore_is_honored() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("crates/sub")).unwrap();
+        fs::write(
+            dir.path().join("crates/sub/.gitignore"),
+            "generated\n",
+        )
+        .unwrap();
+        let ignore = build_ignore(dir.path());
+        let event = DebouncedEvent::Write(
+            dir.path().join("crates/sub/generated/file.rs"),
+        );
+
+        assert_eq!(classify(dir.path(), &ignore, &event), None);
+    }
+
+    #[test]
+    fn test_merge_change_head_wins_regardless_of_order() {
+        assert_eq!(
+            merge_change(
+                Some(RepoChange::Status),
+                Some(RepoChange::Head)
+            ),
+            Some(RepoChange::Head)
+        );
+        assert_eq!(
+            merge_change(
+                Some(RepoChange::Head),
+                Some(RepoChange::Status)
+            ),
+            Some(RepoChange::Head)
+        );
+    }
+
+    #[test]
+    fn test_merge_change_keeps_single_change() {
+        assert_eq!(
+            merge_change(None, Some(RepoChange::Status)),
+            Some(RepoChange::Status)
+        );
+        assert_eq!(
+            merge_change(Some(RepoChange::Status), None),
+            Some(RepoChange::Status)
+        );
+    }
+
+    #[test]
+    fn test_merge_change_none_stays_none() {
+        assert_eq!(merge_change(None, None), None);
+    }
+}